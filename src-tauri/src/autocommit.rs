@@ -0,0 +1,151 @@
+use crate::commit_all_changes_at;
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+const DEFAULT_DEBOUNCE_SECS: u64 = 3;
+
+// How often the watcher thread checks for a stop signal, independent of debounce_secs, so
+// stop_autocommit doesn't wait out a debounce window; it does wait for a commit already in progress.
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+#[derive(Default)]
+pub struct AutocommitState {
+    watcher: Mutex<Option<RecommendedWatcher>>,
+    stop_tx: Mutex<Option<Sender<()>>>,
+    handle: Mutex<Option<JoinHandle<()>>>,
+}
+
+#[tauri::command]
+pub fn start_autocommit(app_handle: AppHandle, debounce_secs: Option<u64>) -> Result<String, String> {
+    let state = app_handle.state::<AutocommitState>();
+    if state.handle.lock().unwrap().is_some() {
+        return Ok("Autocommit is already running".to_string());
+    }
+
+    let app_data = app_handle.path().app_data_dir().map_err(|e| e.to_string())?;
+    let repo_path = app_data.join("excalidraw-local");
+    if !repo_path.join(".git").exists() {
+        return Err("Directory is not a git repository".to_string());
+    }
+
+    let debounce = Duration::from_secs(debounce_secs.unwrap_or(DEFAULT_DEBOUNCE_SECS));
+
+    let (event_tx, event_rx) = channel::<notify::Result<Event>>();
+    let mut watcher = notify::recommended_watcher(event_tx)
+        .map_err(|e| format!("Failed to create filesystem watcher: {}", e))?;
+    watcher.watch(&repo_path, RecursiveMode::Recursive)
+        .map_err(|e| format!("Failed to watch directory: {}", e))?;
+
+    let (stop_tx, stop_rx) = channel::<()>();
+    let watched_path = repo_path.clone();
+    let handle = std::thread::spawn(move || autocommit_loop(watched_path, event_rx, stop_rx, debounce));
+
+    *state.watcher.lock().unwrap() = Some(watcher);
+    *state.stop_tx.lock().unwrap() = Some(stop_tx);
+    *state.handle.lock().unwrap() = Some(handle);
+
+    Ok("Autocommit started".to_string())
+}
+
+#[tauri::command]
+pub fn stop_autocommit(app_handle: AppHandle) -> Result<String, String> {
+    let state = app_handle.state::<AutocommitState>();
+
+    // Drop the watcher first so no further events are queued for the debounce loop.
+    state.watcher.lock().unwrap().take();
+
+    let Some(stop_tx) = state.stop_tx.lock().unwrap().take() else {
+        return Ok("Autocommit is not running".to_string());
+    };
+    let _ = stop_tx.send(());
+
+    // Take the handle out of the mutex before joining, so the lock isn't held for the
+    // duration of the join and a concurrent `start_autocommit` can't deadlock on it.
+    let handle = state.handle.lock().unwrap().take();
+    if let Some(handle) = handle {
+        handle.join().map_err(|_| "Failed to stop autocommit thread".to_string())?;
+    }
+
+    Ok("Autocommit stopped".to_string())
+}
+
+enum Wait {
+    Relevant,
+    Timeout,
+    Disconnected,
+}
+
+// Waits up to `timeout` for an event outside `git_dir`, skipping our own index/ref/log writes
+// so they don't reset the debounce or retrigger another commit.
+fn wait_for_relevant_event(event_rx: &Receiver<notify::Result<Event>>, git_dir: &Path, timeout: Duration) -> Wait {
+    let deadline = std::time::Instant::now() + timeout;
+    loop {
+        let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+        match event_rx.recv_timeout(remaining) {
+            Ok(Ok(event)) => {
+                if event.paths.iter().any(|p| !p.starts_with(git_dir)) {
+                    return Wait::Relevant;
+                }
+                // Only `.git` internals changed (our own commit); keep waiting.
+            }
+            Ok(Err(_)) => return Wait::Relevant,
+            Err(RecvTimeoutError::Timeout) => return Wait::Timeout,
+            Err(RecvTimeoutError::Disconnected) => return Wait::Disconnected,
+        }
+    }
+}
+
+fn autocommit_loop(
+    repo_path: PathBuf,
+    event_rx: Receiver<notify::Result<Event>>,
+    stop_rx: Receiver<()>,
+    debounce: Duration,
+) {
+    let git_dir = repo_path.join(".git");
+
+    loop {
+        match wait_for_relevant_event(&event_rx, &git_dir, STOP_POLL_INTERVAL) {
+            Wait::Relevant => {
+                // An event arrived; keep polling until the working tree has been quiet for `debounce`.
+                let mut quiet_since = std::time::Instant::now();
+                loop {
+                    if stop_rx.try_recv().is_ok() {
+                        return;
+                    }
+                    match wait_for_relevant_event(&event_rx, &git_dir, STOP_POLL_INTERVAL) {
+                        Wait::Relevant => quiet_since = std::time::Instant::now(),
+                        Wait::Timeout => {
+                            if quiet_since.elapsed() >= debounce {
+                                break;
+                            }
+                        }
+                        Wait::Disconnected => return,
+                    }
+                }
+
+                let message = format!("Auto-save {}", current_timestamp());
+                if let Err(e) = commit_all_changes_at(&repo_path, &message) {
+                    eprintln!("Autocommit failed: {}", e);
+                }
+            }
+            Wait::Timeout => {
+                if stop_rx.try_recv().is_ok() {
+                    return;
+                }
+            }
+            Wait::Disconnected => return,
+        }
+    }
+}
+
+fn current_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
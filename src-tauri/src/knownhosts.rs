@@ -0,0 +1,150 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use git2::cert::CertificateCheckStatus;
+use git2::{Cert, Error as GitError};
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use std::io::Write;
+use std::path::Path;
+
+type HmacSha1 = Hmac<Sha1>;
+
+// Unknown host: fails with an error prefixed `UNKNOWN_HOST_KEY:<host>:<fingerprint>` so the
+// frontend can prompt the user and call confirm_host_key before retrying.
+pub(crate) fn check_host_key(
+    known_hosts_path: &Path,
+    cert: &Cert,
+    host: &str,
+) -> Result<CertificateCheckStatus, GitError> {
+    let hostkey = cert
+        .as_hostkey()
+        .ok_or_else(|| GitError::from_str("Only SSH host keys are supported"))?;
+    let fingerprint = hostkey_fingerprint(hostkey)
+        .ok_or_else(|| GitError::from_str("Host key has no usable fingerprint"))?;
+
+    evaluate_host_key(known_hosts_path, host, &fingerprint)
+}
+
+// Split out of check_host_key so it can be tested without a real git2::Cert.
+fn evaluate_host_key(known_hosts_path: &Path, host: &str, fingerprint: &str) -> Result<CertificateCheckStatus, GitError> {
+    match stored_fingerprint(known_hosts_path, host) {
+        Some(stored) if stored == fingerprint => Ok(CertificateCheckStatus::CertificateOk),
+        Some(_) => Err(GitError::from_str(&format!(
+            "Host key for {} does not match the one on record (possible man-in-the-middle attack); refusing to connect",
+            host
+        ))),
+        None => Err(GitError::from_str(&format!("UNKNOWN_HOST_KEY:{}:{}", host, fingerprint))),
+    }
+}
+
+fn hostkey_fingerprint(hostkey: &git2::cert::CertHostkey) -> Option<String> {
+    if let Some(hash) = hostkey.hash_sha256() {
+        return Some(format!("sha256:{}", BASE64.encode(hash)));
+    }
+    hostkey.hash_sha1().map(|hash| format!("sha1:{}", BASE64.encode(hash)))
+}
+
+pub(crate) fn trust_host(known_hosts_path: &Path, host: &str, fingerprint: &str) -> Result<(), String> {
+    if let Some(parent) = known_hosts_path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create .ssh directory: {}", e))?;
+    }
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(known_hosts_path)
+        .map_err(|e| format!("Failed to open known_hosts: {}", e))?;
+
+    writeln!(file, "{} {}", host, fingerprint).map_err(|e| format!("Failed to write known_hosts: {}", e))
+}
+
+// Each known_hosts line is `<hostname-or-hashed-hostname> <fingerprint>`.
+fn stored_fingerprint(known_hosts_path: &Path, host: &str) -> Option<String> {
+    let contents = std::fs::read_to_string(known_hosts_path).ok()?;
+
+    contents.lines().find_map(|line| {
+        let line = line.trim();
+        let mut parts = line.splitn(2, char::is_whitespace);
+        let host_part = parts.next()?;
+        let fingerprint_part = parts.next()?.trim();
+
+        if host_part.is_empty() || fingerprint_part.is_empty() {
+            return None;
+        }
+        if line_host_matches(host_part, host) {
+            Some(fingerprint_part.to_string())
+        } else {
+            None
+        }
+    })
+}
+
+// Supports plain `hostname` entries and hashed `|1|<base64-salt>|<base64-hash>` entries
+// (HMAC-SHA1 of the hostname keyed by the decoded salt).
+fn line_host_matches(host_part: &str, host: &str) -> bool {
+    let Some(hashed) = host_part.strip_prefix("|1|") else {
+        return host_part == host;
+    };
+
+    let mut parts = hashed.splitn(2, '|');
+    let (Some(salt_b64), Some(hash_b64)) = (parts.next(), parts.next()) else {
+        return false;
+    };
+
+    let (Ok(salt), Ok(expected)) = (BASE64.decode(salt_b64), BASE64.decode(hash_b64)) else {
+        return false;
+    };
+
+    let Ok(mut mac) = HmacSha1::new_from_slice(&salt) else {
+        return false;
+    };
+    mac.update(host.as_bytes());
+    mac.verify_slice(&expected).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn known_hosts_with(name: &str, contents: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("excalidraw-local-knownhosts-test-{}", name));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn matches_plain_hostname() {
+        let path = known_hosts_with("plain", "example.com sha256:abc\n");
+        let status = evaluate_host_key(&path, "example.com", "sha256:abc").unwrap();
+        assert!(matches!(status, CertificateCheckStatus::CertificateOk));
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn matches_hashed_hostname() {
+        let mut mac = HmacSha1::new_from_slice(b"somesalt").unwrap();
+        mac.update(b"example.com");
+        let hash = mac.finalize().into_bytes();
+        let line = format!("|1|{}|{} sha256:abc\n", BASE64.encode(b"somesalt"), BASE64.encode(hash));
+        let path = known_hosts_with("hashed", &line);
+        let status = evaluate_host_key(&path, "example.com", "sha256:abc").unwrap();
+        assert!(matches!(status, CertificateCheckStatus::CertificateOk));
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn rejects_unknown_host() {
+        let path = known_hosts_with("unknown", "other.com sha256:abc\n");
+        let err = evaluate_host_key(&path, "example.com", "sha256:xyz").unwrap_err();
+        assert!(err.message().starts_with("UNKNOWN_HOST_KEY:example.com:"));
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn rejects_mismatched_fingerprint() {
+        let path = known_hosts_with("mismatch", "example.com sha256:abc\n");
+        let err = evaluate_host_key(&path, "example.com", "sha256:xyz").unwrap_err();
+        assert!(err.message().contains("does not match the one on record"));
+        std::fs::remove_file(&path).ok();
+    }
+}
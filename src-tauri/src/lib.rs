@@ -1,6 +1,18 @@
+mod autocommit;
+mod knownhosts;
+mod signing;
+mod sync;
+
 use git2::{Repository, Signature};
 use std::path::Path;
-use tauri::{AppHandle, Manager};
+use tauri::{AppHandle, Emitter, Manager};
+
+#[derive(Clone, serde::Serialize)]
+struct TransferProgress {
+    total_objects: usize,
+    received_objects: usize,
+    received_bytes: usize,
+}
 
 #[tauri::command]
 fn init_git_repo(app_handle: AppHandle) -> Result<String, String> {
@@ -27,14 +39,33 @@ fn init_git_repo(app_handle: AppHandle) -> Result<String, String> {
 fn commit_all_changes(app_handle: AppHandle, message: String) -> Result<String, String> {
     let app_data = app_handle.path().app_data_dir().map_err(|e| e.to_string())?;
     let repo_path = app_data.join("excalidraw-local");
-    
+
+    commit_all_changes_at(&repo_path, &message).map(|c| c.summary().to_string())
+}
+
+pub(crate) enum CommitOutcome {
+    Committed,
+    NothingToCommit,
+}
+
+impl CommitOutcome {
+    fn summary(&self) -> &'static str {
+        match self {
+            CommitOutcome::Committed => "All changes committed successfully",
+            CommitOutcome::NothingToCommit => "No changes to commit",
+        }
+    }
+}
+
+// Shared by the `commit_all_changes` command and the autocommit watcher.
+pub(crate) fn commit_all_changes_at(repo_path: &Path, message: &str) -> Result<CommitOutcome, String> {
     // Open the repository
-    let repo = Repository::open(repo_path.clone()).map_err(|e| format!("Failed to open repository: {}", e))?;
-    
+    let repo = Repository::open(repo_path).map_err(|e| format!("Failed to open repository: {}", e))?;
+
     // Create the signature for the commit
     let signature = Signature::now("Excalidraw Local", "excalidraw@local.app")
         .map_err(|e| format!("Failed to create signature: {}", e))?;
-    
+
     // Add all files to index
     let mut index = repo.index().map_err(|e| format!("Failed to get index: {}", e))?;
     index.add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
@@ -42,37 +73,28 @@ fn commit_all_changes(app_handle: AppHandle, message: String) -> Result<String,
     index.update_all(["*"].iter(), None)
         .map_err(|e| format!("Failed to update index: {}", e))?;
     index.write().map_err(|e| format!("Failed to write index: {}", e))?;
-    
+
     // Create tree from index
     let tree_id = index.write_tree().map_err(|e| format!("Failed to write tree: {}", e))?;
     let tree = repo.find_tree(tree_id).map_err(|e| format!("Failed to find tree: {}", e))?;
-    
+
     let parent_commit = match repo.head() {
         Ok(head) => head.peel_to_commit().map_err(|e| format!("Failed to peel to commit: {}", e))?,
         Err(_) => {
-            return repo.commit(
-                Some("HEAD"),
-                &signature,
-                &signature,
-                &message,
-                &tree,
-                &[]
-            )
-            .map_err(|e| format!("Failed to commit: {}", e))
-            .map(|_| "All changes committed successfully".to_string());
+            signing::create_commit(&repo, repo_path, &signature, message, &tree, &[])?;
+            return Ok(CommitOutcome::Committed);
         }
     };
-    
-    repo.commit(
-        Some("HEAD"),
-        &signature,
-        &signature,
-        &message,
-        &tree,
-        &[&parent_commit]
-    )
-    .map_err(|e| format!("Failed to commit: {}", e))
-    .map(|_| "All changes committed successfully".to_string())
+
+    // Skip committing when nothing actually changed, so the autocommit watcher doesn't
+    // fill the history with empty "Auto-save" commits.
+    if tree_id == parent_commit.tree_id() {
+        return Ok(CommitOutcome::NothingToCommit);
+    }
+
+    signing::create_commit(&repo, repo_path, &signature, message, &tree, &[&parent_commit])?;
+
+    Ok(CommitOutcome::Committed)
 }
 
 #[tauri::command]
@@ -80,9 +102,11 @@ fn get_file_history(app_handle: AppHandle, file_path: String) -> Result<Vec<Hist
     let app_data = app_handle.path().app_data_dir().map_err(|e| e.to_string())?;
     let repo_path = app_data.join("excalidraw-local");
     
-    let repo = Repository::open(repo_path).map_err(|e| format!("Failed to open repository: {}", e))?;
+    let repo = Repository::open(&repo_path).map_err(|e| format!("Failed to open repository: {}", e))?;
+    let branch = current_branch_name(&repo)?;
     let mut revwalk = repo.revwalk().map_err(|e| format!("Failed to create revwalk: {}", e))?;
-    revwalk.push_head().map_err(|e| format!("Failed to push head: {}", e))?;
+    revwalk.push_ref(&format!("refs/heads/{}", branch))
+        .map_err(|e| format!("Failed to push branch ref: {}", e))?;
     
     let relative_path = Path::new(&file_path).file_name().ok_or("Invalid file path")?;
     let rel_path_str = relative_path.to_str().ok_or("Invalid path string")?;
@@ -101,6 +125,7 @@ fn get_file_history(app_handle: AppHandle, file_path: String) -> Result<Vec<Hist
                     message: commit.message().unwrap_or("").to_string(),
                     timestamp: commit.time().seconds(),
                     author: commit.author().name().unwrap_or("Unknown").to_string(),
+                    signature_status: signing::verify_commit_signature(&repo, &repo_path, commit_id),
                 });
             }
         }
@@ -109,6 +134,68 @@ fn get_file_history(app_handle: AppHandle, file_path: String) -> Result<Vec<Hist
     Ok(history)
 }
 
+pub(crate) fn current_branch_name(repo: &Repository) -> Result<String, String> {
+    let head = repo.find_reference("HEAD").map_err(|e| format!("Failed to read HEAD: {}", e))?;
+    let target = head.symbolic_target().ok_or("HEAD is not a symbolic reference")?;
+    target
+        .strip_prefix("refs/heads/")
+        .map(|name| name.to_string())
+        .ok_or_else(|| format!("Could not parse branch name from {}", target))
+}
+
+#[tauri::command]
+fn list_branches(app_handle: AppHandle) -> Result<Vec<String>, String> {
+    let app_data = app_handle.path().app_data_dir().map_err(|e| e.to_string())?;
+    let repo_path = app_data.join("excalidraw-local");
+
+    let repo = Repository::open(&repo_path).map_err(|e| format!("Failed to open repository: {}", e))?;
+    let branches = repo.branches(Some(git2::BranchType::Local))
+        .map_err(|e| format!("Failed to list branches: {}", e))?;
+
+    branches
+        .map(|b| {
+            let (branch, _) = b.map_err(|e| format!("Failed to read branch: {}", e))?;
+            branch
+                .name()
+                .map_err(|e| format!("Failed to read branch name: {}", e))?
+                .map(|name| name.to_string())
+                .ok_or_else(|| "Branch name was not valid UTF-8".to_string())
+        })
+        .collect()
+}
+
+#[tauri::command]
+fn checkout_branch(app_handle: AppHandle, branch: String) -> Result<String, String> {
+    let app_data = app_handle.path().app_data_dir().map_err(|e| e.to_string())?;
+    let repo_path = app_data.join("excalidraw-local");
+
+    let repo = Repository::open(&repo_path).map_err(|e| format!("Failed to open repository: {}", e))?;
+    ensure_clean_working_tree(&repo)?;
+
+    let branch_ref = format!("refs/heads/{}", branch);
+    let reference = repo.find_reference(&branch_ref).map_err(|e| format!("Branch '{}' not found: {}", branch, e))?;
+    let commit = reference.peel_to_commit().map_err(|e| format!("Failed to read branch commit: {}", e))?;
+
+    repo.checkout_tree(commit.as_object(), Some(git2::build::CheckoutBuilder::default().force()))
+        .map_err(|e| format!("Failed to checkout branch: {}", e))?;
+    repo.set_head(&branch_ref).map_err(|e| format!("Failed to update HEAD: {}", e))?;
+
+    Ok(format!("Switched to branch '{}'", branch))
+}
+
+// Refuses to proceed with a forced checkout while the working tree has uncommitted changes,
+// since autocommit's debounce window otherwise leaves a gap where edits can be silently lost.
+pub(crate) fn ensure_clean_working_tree(repo: &Repository) -> Result<(), String> {
+    let mut options = git2::StatusOptions::new();
+    options.include_untracked(true);
+    let statuses = repo.statuses(Some(&mut options)).map_err(|e| format!("Failed to read working tree status: {}", e))?;
+    if statuses.is_empty() {
+        Ok(())
+    } else {
+        Err("Working tree has uncommitted changes; commit or discard them first".to_string())
+    }
+}
+
 // Helper function to check if a commit modified a specific file
 fn diff_for_commit(repo: &Repository, commit: &git2::Commit, path: &str) -> Result<bool, git2::Error> {
     let parent = if commit.parents().len() > 0 {
@@ -153,6 +240,8 @@ struct HistoryEntry {
     message: String,
     timestamp: i64,
     author: String,
+    // "good", "bad", or "missing" - see signing::verify_commit_signature
+    signature_status: String,
 }
 
 #[tauri::command]
@@ -185,14 +274,29 @@ fn restore_version(app_handle: AppHandle, file_path: String, commit_id: String)
 fn set_git_remote(app_handle: AppHandle, url: String) -> Result<String, String> {
     let app_data = app_handle.path().app_data_dir().map_err(|e| e.to_string())?;
     let repo_path = app_data.join("excalidraw-local");
-    
+    let url = normalize_remote_url(&url);
+
     let repo = Repository::open(repo_path).map_err(|e| format!("Failed to open repository: {}", e))?;
     match repo.find_remote("origin") {
         Ok(_) => repo.remote_set_url("origin", &url).map_err(|e| format!("Failed to update remote URL: {}", e))?,
         Err(_) => { repo.remote("origin", &url).map_err(|e| format!("Failed to create remote: {}", e))?; }
     }
-    
-    Ok("Git remote set successfully".to_string())
+
+    Ok(url)
+}
+
+// Expands gh:/gl: shorthand into full SSH URLs; leaves already-complete URLs unchanged.
+fn normalize_remote_url(url: &str) -> String {
+    let (host, path) = if let Some(path) = url.strip_prefix("gh:") {
+        ("git@github.com:", path)
+    } else if let Some(path) = url.strip_prefix("gl:") {
+        ("git@gitlab.com:", path)
+    } else {
+        return url.to_string();
+    };
+
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    format!("{}{}.git", host, path)
 }
 
 #[tauri::command]
@@ -200,7 +304,7 @@ fn push_to_remote(app_handle: AppHandle, ssh_key_path: Option<String>) -> Result
     let app_data = app_handle.path().app_data_dir().map_err(|e| e.to_string())?;
     let repo_path = app_data.join("excalidraw-local");
     
-    let repo = Repository::open(repo_path).map_err(|e| format!("Failed to open repository: {}", e))?;
+    let repo = Repository::open(&repo_path).map_err(|e| format!("Failed to open repository: {}", e))?;
     let mut callbacks = git2::RemoteCallbacks::new();
     let key_path = ssh_key_path.clone();
     callbacks.credentials(move |_url, username_from_url, allowed_types| {
@@ -215,18 +319,61 @@ fn push_to_remote(app_handle: AppHandle, ssh_key_path: Option<String>) -> Result
             Err(git2::Error::from_str("No supported authentication method"))
         }
     });
+
+    let known_hosts_path = repo_path.join(".ssh").join("known_hosts");
+    callbacks.certificate_check(move |cert, host| knownhosts::check_host_key(&known_hosts_path, cert, host));
+
+    let progress_handle = app_handle.clone();
+    callbacks.transfer_progress(move |progress| {
+        let _ = progress_handle.emit("git://progress", TransferProgress {
+            total_objects: progress.total_objects(),
+            received_objects: progress.received_objects(),
+            received_bytes: progress.received_bytes(),
+        });
+        true
+    });
+
+    let push_progress_handle = app_handle.clone();
+    callbacks.push_transfer_progress(move |current, total, bytes| {
+        let _ = push_progress_handle.emit("git://progress", TransferProgress {
+            total_objects: total,
+            received_objects: current,
+            received_bytes: bytes,
+        });
+    });
+
+    // `push` can report a ref as rejected without the call itself erroring, so any non-`None`
+    // status here must be surfaced as a failure.
+    let ref_status: std::sync::Arc<std::sync::Mutex<Option<String>>> = std::sync::Arc::new(std::sync::Mutex::new(None));
+    let ref_status_handle = ref_status.clone();
+    callbacks.push_update_reference(move |refname, status| {
+        if let Some(status) = status {
+            *ref_status_handle.lock().unwrap() = Some(format!("{}: {}", refname, status));
+        }
+        Ok(())
+    });
+
     let mut push_options = git2::PushOptions::new();
     push_options.remote_callbacks(callbacks);
-    
+
+    let branch = current_branch_name(&repo)?;
+    let refspec = format!("refs/heads/{0}:refs/heads/{0}", branch);
+
     let mut remote = repo.find_remote("origin").map_err(|e| format!("Failed to find remote 'origin': {}", e))?;
-    remote.push(&["refs/heads/master:refs/heads/master"], Some(&mut push_options))
+    remote.push(&[&refspec], Some(&mut push_options))
         .map_err(|e| format!("Failed to push to remote: {}", e))?;
-    
+
+    if let Some(rejected) = ref_status.lock().unwrap().take() {
+        return Err(format!("Remote rejected ref update: {}", rejected));
+    }
+
     Ok("Successfully pushed to remote".to_string())
 }
 
 #[tauri::command]
 fn test_git_connection(app_handle: AppHandle, url: String, username: String, email: String, ssh_key_path: Option<String>) -> Result<bool, String> {
+    let url = normalize_remote_url(&url);
+
     // Check if URL is SSH format
     if !url.starts_with("git@") && !url.starts_with("ssh://") {
         return Err("Only SSH URLs are supported (e.g., git@github.com:username/repo.git). Please use an SSH URL instead.".to_string());
@@ -242,7 +389,7 @@ fn test_git_connection(app_handle: AppHandle, url: String, username: String, ema
         }
     }
     
-    let repo = Repository::open(repo_path).map_err(|e| format!("Failed to open repository: {}", e))?;
+    let repo = Repository::open(&repo_path).map_err(|e| format!("Failed to open repository: {}", e))?;
     let mut config = repo.config().map_err(|e| format!("Failed to get config: {}", e))?;
     
     config.set_str("user.name", &username).map_err(|e| format!("Failed to set username: {}", e))?;
@@ -279,7 +426,20 @@ fn test_git_connection(app_handle: AppHandle, url: String, username: String, ema
             Err(git2::Error::from_str(&format!("SSH authentication is required but allowed types are: {:?}", allowed_types)))
         }
     });
-    
+
+    let known_hosts_path = repo_path.join(".ssh").join("known_hosts");
+    callbacks.certificate_check(move |cert, host| knownhosts::check_host_key(&known_hosts_path, cert, host));
+
+    let progress_handle = app_handle.clone();
+    callbacks.transfer_progress(move |progress| {
+        let _ = progress_handle.emit("git://progress", TransferProgress {
+            total_objects: progress.total_objects(),
+            received_objects: progress.received_objects(),
+            received_bytes: progress.received_bytes(),
+        });
+        true
+    });
+
     // Create fetch options with callbacks
     let mut fetch_options = git2::FetchOptions::new();
     fetch_options.remote_callbacks(callbacks);
@@ -327,11 +487,23 @@ fn generate_ssh_key(app_handle: AppHandle, email: String) -> Result<(String, Str
     Ok((public_key, key_path_str.to_string()))
 }
 
+#[tauri::command]
+fn confirm_host_key(app_handle: AppHandle, host: String, fingerprint: String) -> Result<String, String> {
+    let app_data = app_handle.path().app_data_dir().map_err(|e| e.to_string())?;
+    let repo_path = app_data.join("excalidraw-local");
+    let known_hosts_path = repo_path.join(".ssh").join("known_hosts");
+
+    knownhosts::trust_host(&known_hosts_path, &host, &fingerprint)?;
+
+    Ok(format!("Host key for {} trusted", host))
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_opener::init())
+        .manage(autocommit::AutocommitState::default())
         .invoke_handler(tauri::generate_handler![
             init_git_repo,
             commit_all_changes,
@@ -340,7 +512,14 @@ pub fn run() {
             set_git_remote,
             push_to_remote,
             test_git_connection,
-            generate_ssh_key
+            generate_ssh_key,
+            confirm_host_key,
+            list_branches,
+            checkout_branch,
+            autocommit::start_autocommit,
+            autocommit::stop_autocommit,
+            sync::clone_repo,
+            sync::pull_from_remote
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
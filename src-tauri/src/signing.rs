@@ -0,0 +1,145 @@
+use git2::{Commit, Oid, Repository, Signature, Tree};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+const SIGNING_KEY_NAME: &str = "excalidraw_deploy_key";
+const SIGNING_IDENTITY: &str = "excalidraw@local.app";
+
+// Signs with the repo's SSH deploy key when commit.gpgsign + gpg.format=ssh are set (mirrors
+// `git commit -S`); falls back to a plain commit otherwise.
+pub(crate) fn create_commit(
+    repo: &Repository,
+    repo_path: &Path,
+    signature: &Signature,
+    message: &str,
+    tree: &Tree,
+    parents: &[&Commit],
+) -> Result<(), String> {
+    let config = repo.config().map_err(|e| format!("Failed to get config: {}", e))?;
+    let should_sign = config.get_bool("commit.gpgsign").unwrap_or(false)
+        && config.get_string("gpg.format").map(|f| f == "ssh").unwrap_or(false);
+
+    if !should_sign {
+        repo.commit(Some("HEAD"), signature, signature, message, tree, parents)
+            .map_err(|e| format!("Failed to commit: {}", e))?;
+        return Ok(());
+    }
+
+    let buffer = repo.commit_create_buffer(signature, signature, message, tree, parents)
+        .map_err(|e| format!("Failed to build commit buffer: {}", e))?;
+    let buffer_str = buffer.as_str().ok_or("Commit buffer was not valid UTF-8")?;
+
+    let signature_str = sign_buffer(repo_path, buffer_str)?;
+    let oid = repo.commit_signed(buffer_str, &signature_str, Some("gpgsig"))
+        .map_err(|e| format!("Failed to create signed commit: {}", e))?;
+
+    update_head(repo, oid, message)
+}
+
+// commit_signed doesn't update any ref, so point the branch HEAD refers to at oid ourselves.
+fn update_head(repo: &Repository, oid: Oid, message: &str) -> Result<(), String> {
+    let head_ref = repo.find_reference("HEAD").map_err(|e| format!("Failed to read HEAD: {}", e))?;
+    let branch_ref = head_ref.symbolic_target().ok_or("HEAD is not a symbolic reference")?.to_string();
+    repo.reference(&branch_ref, oid, true, message)
+        .map_err(|e| format!("Failed to update {}: {}", branch_ref, e))?;
+    Ok(())
+}
+
+fn sign_buffer(repo_path: &Path, buffer: &str) -> Result<String, String> {
+    let key_path = repo_path.join(".ssh").join(SIGNING_KEY_NAME);
+    if !key_path.exists() {
+        return Err("No signing key found; run generate_ssh_key first".to_string());
+    }
+
+    let buf_path = repo_path.join(".git").join("COMMIT_SIGN_BUFFER");
+    std::fs::write(&buf_path, buffer).map_err(|e| format!("Failed to write commit buffer: {}", e))?;
+
+    let result = Command::new("ssh-keygen")
+        .args(["-Y", "sign", "-n", "git", "-f"])
+        .arg(&key_path)
+        .arg(&buf_path)
+        .output();
+
+    let sig_path = repo_path.join(".git").join("COMMIT_SIGN_BUFFER.sig");
+    let signature = result
+        .map_err(|e| format!("Failed to execute ssh-keygen: {}", e))
+        .and_then(|output| {
+            if output.status.success() {
+                std::fs::read_to_string(&sig_path).map_err(|e| format!("Failed to read signature: {}", e))
+            } else {
+                Err(format!("ssh-keygen signing failed: {}", String::from_utf8_lossy(&output.stderr)))
+            }
+        });
+
+    std::fs::remove_file(&buf_path).ok();
+    std::fs::remove_file(&sig_path).ok();
+
+    signature
+}
+
+// Returns "good", "bad", or "missing".
+pub(crate) fn verify_commit_signature(repo: &Repository, repo_path: &Path, commit_id: Oid) -> String {
+    let (signature, signed_data) = match repo.extract_signature(&commit_id, Some("gpgsig")) {
+        Ok(pair) => pair,
+        Err(_) => return "missing".to_string(),
+    };
+
+    let (Some(signature), Some(signed_data)) = (signature.as_str(), signed_data.as_str()) else {
+        return "bad".to_string();
+    };
+
+    let pub_key_path = repo_path.join(".ssh").join(format!("{}.pub", SIGNING_KEY_NAME));
+    match verify_ssh_signature(repo_path, &pub_key_path, signature, signed_data) {
+        Ok(true) => "good".to_string(),
+        _ => "bad".to_string(),
+    }
+}
+
+fn verify_ssh_signature(
+    repo_path: &Path,
+    pub_key_path: &Path,
+    signature: &str,
+    signed_data: &str,
+) -> Result<bool, String> {
+    if !pub_key_path.exists() {
+        return Ok(false);
+    }
+    let public_key = std::fs::read_to_string(pub_key_path)
+        .map_err(|e| format!("Failed to read public key: {}", e))?;
+
+    let allowed_signers_path = repo_path.join(".git").join("ALLOWED_SIGNERS");
+    std::fs::write(
+        &allowed_signers_path,
+        format!("{} namespaces=\"git\" {}", SIGNING_IDENTITY, public_key.trim()),
+    )
+    .map_err(|e| format!("Failed to write allowed signers file: {}", e))?;
+
+    let sig_path = repo_path.join(".git").join("VERIFY_SIGNATURE.sig");
+    std::fs::write(&sig_path, signature).map_err(|e| format!("Failed to write signature: {}", e))?;
+
+    let mut child = Command::new("ssh-keygen")
+        .args(["-Y", "verify", "-f"])
+        .arg(&allowed_signers_path)
+        .args(["-I", SIGNING_IDENTITY, "-n", "git", "-s"])
+        .arg(&sig_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to execute ssh-keygen: {}", e))?;
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(signed_data.as_bytes())
+        .map_err(|e| format!("Failed to write signed data: {}", e))?;
+
+    let status = child.wait().map_err(|e| format!("Failed to wait on ssh-keygen: {}", e))?;
+
+    std::fs::remove_file(&allowed_signers_path).ok();
+    std::fs::remove_file(&sig_path).ok();
+
+    Ok(status.success())
+}
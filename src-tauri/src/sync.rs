@@ -0,0 +1,151 @@
+use crate::{knownhosts, signing};
+use git2::build::{CheckoutBuilder, RepoBuilder};
+use git2::{FetchOptions, RemoteCallbacks, Repository, Signature};
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+
+// status is one of "up-to-date", "fast-forwarded", "merged", or "conflict"; conflicts lists
+// paths needing manual resolution when status is "conflict".
+#[derive(serde::Serialize)]
+pub struct PullResult {
+    status: String,
+    conflicts: Vec<String>,
+}
+
+fn remote_callbacks(repo_path: &Path, ssh_key_path: Option<String>) -> RemoteCallbacks<'static> {
+    let mut callbacks = RemoteCallbacks::new();
+
+    let key_path = ssh_key_path.clone();
+    callbacks.credentials(move |_url, username_from_url, allowed_types| {
+        let username = username_from_url.unwrap_or("git");
+        if allowed_types.contains(git2::CredentialType::SSH_KEY) {
+            if let Some(ref path) = key_path {
+                git2::Cred::ssh_key(username, None, std::path::Path::new(path), None)
+            } else {
+                git2::Cred::ssh_key_from_agent(username)
+            }
+        } else {
+            Err(git2::Error::from_str("No supported authentication method"))
+        }
+    });
+
+    let known_hosts_path = repo_path.join(".ssh").join("known_hosts");
+    callbacks.certificate_check(move |cert, host| knownhosts::check_host_key(&known_hosts_path, cert, host));
+
+    callbacks
+}
+
+#[tauri::command]
+pub fn clone_repo(app_handle: AppHandle, url: String, ssh_key_path: Option<String>) -> Result<String, String> {
+    let app_data = app_handle.path().app_data_dir().map_err(|e| e.to_string())?;
+    let repo_path = app_data.join("excalidraw-local");
+
+    if repo_path.join(".git").exists() {
+        return Err("Directory already contains a git repository".to_string());
+    }
+    if repo_path.exists() {
+        let has_entries = repo_path
+            .read_dir()
+            .map_err(|e| format!("Failed to read directory: {}", e))?
+            .next()
+            .is_some();
+        if has_entries {
+            return Err("Directory is not empty".to_string());
+        }
+    }
+
+    let callbacks = remote_callbacks(&repo_path, ssh_key_path);
+    let mut fetch_options = FetchOptions::new();
+    fetch_options.remote_callbacks(callbacks);
+
+    RepoBuilder::new()
+        .fetch_options(fetch_options)
+        .clone(&url, &repo_path)
+        .map_err(|e| format!("Failed to clone repository: {}", e))?;
+
+    Ok("Repository cloned successfully".to_string())
+}
+
+#[tauri::command]
+pub fn pull_from_remote(app_handle: AppHandle, ssh_key_path: Option<String>) -> Result<PullResult, String> {
+    let app_data = app_handle.path().app_data_dir().map_err(|e| e.to_string())?;
+    let repo_path = app_data.join("excalidraw-local");
+
+    let repo = Repository::open(&repo_path).map_err(|e| format!("Failed to open repository: {}", e))?;
+    crate::ensure_clean_working_tree(&repo)?;
+
+    let callbacks = remote_callbacks(&repo_path, ssh_key_path);
+    let mut fetch_options = FetchOptions::new();
+    fetch_options.remote_callbacks(callbacks);
+
+    let mut remote = repo.find_remote("origin").map_err(|e| format!("Failed to find remote 'origin': {}", e))?;
+    remote.fetch(&[] as &[&str], Some(&mut fetch_options), None)
+        .map_err(|e| format!("Failed to fetch from remote: {}", e))?;
+
+    let branch = crate::current_branch_name(&repo)?;
+    let remote_ref = format!("refs/remotes/origin/{}", branch);
+    let local_ref = format!("refs/heads/{}", branch);
+
+    let remote_branch = repo.find_reference(&remote_ref)
+        .map_err(|e| format!("Failed to find remote tracking branch: {}", e))?;
+    let fetch_commit = repo.reference_to_annotated_commit(&remote_branch)
+        .map_err(|e| format!("Failed to resolve remote tracking commit: {}", e))?;
+
+    let (analysis, _) = repo.merge_analysis(&[&fetch_commit])
+        .map_err(|e| format!("Failed to analyze merge: {}", e))?;
+
+    if analysis.is_up_to_date() {
+        return Ok(PullResult { status: "up-to-date".to_string(), conflicts: vec![] });
+    }
+
+    if analysis.is_fast_forward() {
+        let mut local_branch = repo.find_reference(&local_ref)
+            .map_err(|e| format!("Failed to find local branch: {}", e))?;
+        local_branch.set_target(fetch_commit.id(), "Fast-forward pull")
+            .map_err(|e| format!("Failed to fast-forward: {}", e))?;
+        repo.set_head(&local_ref).map_err(|e| format!("Failed to update HEAD: {}", e))?;
+        repo.checkout_head(Some(CheckoutBuilder::default().force()))
+            .map_err(|e| format!("Failed to checkout fast-forwarded tree: {}", e))?;
+        return Ok(PullResult { status: "fast-forwarded".to_string(), conflicts: vec![] });
+    }
+
+    let local_commit = repo.head()
+        .and_then(|head| head.peel_to_commit())
+        .map_err(|e| format!("Failed to read local HEAD: {}", e))?;
+    let fetch_commit_obj = repo.find_commit(fetch_commit.id())
+        .map_err(|e| format!("Failed to read fetched commit: {}", e))?;
+
+    let mut merge_index = repo.merge_commits(&local_commit, &fetch_commit_obj, None)
+        .map_err(|e| format!("Failed to merge: {}", e))?;
+
+    if merge_index.has_conflicts() {
+        let conflicts = merge_index
+            .conflicts()
+            .map_err(|e| format!("Failed to read conflicts: {}", e))?
+            .filter_map(|c| c.ok())
+            .filter_map(|c| c.our.or(c.their))
+            .filter_map(|entry| std::str::from_utf8(&entry.path).ok().map(|p| p.to_string()))
+            .collect::<Vec<_>>();
+
+        return Ok(PullResult { status: "conflict".to_string(), conflicts });
+    }
+
+    let tree_id = merge_index.write_tree_to(&repo).map_err(|e| format!("Failed to write merged tree: {}", e))?;
+    let tree = repo.find_tree(tree_id).map_err(|e| format!("Failed to find merged tree: {}", e))?;
+    let signature = Signature::now("Excalidraw Local", "excalidraw@local.app")
+        .map_err(|e| format!("Failed to create signature: {}", e))?;
+
+    signing::create_commit(
+        &repo,
+        &repo_path,
+        &signature,
+        "Merge remote changes",
+        &tree,
+        &[&local_commit, &fetch_commit_obj],
+    )?;
+
+    repo.checkout_head(Some(CheckoutBuilder::default().force()))
+        .map_err(|e| format!("Failed to checkout merged tree: {}", e))?;
+
+    Ok(PullResult { status: "merged".to_string(), conflicts: vec![] })
+}